@@ -0,0 +1,258 @@
+//! Security-audit checks that run against a parsed policy and flag common
+//! CSP weaknesses, for use in CI via the `--audit` flag.
+
+use crate::directive::Directive;
+use crate::tokenizer::{tokenize, SourceExpression};
+use crate::Row;
+use colored::Colorize;
+use std::fmt;
+
+const FETCH_DIRECTIVES: &[&str] = &[
+    "default-src",
+    "script-src",
+    "style-src",
+    "img-src",
+    "connect-src",
+    "font-src",
+    "object-src",
+    "media-src",
+    "frame-src",
+    "worker-src",
+    "manifest-src",
+    "child-src",
+];
+
+/// How serious a finding is, and what it should do to the process exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Severity {
+    Note,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub(crate) fn exit_code(&self) -> u8 {
+        match self {
+            Severity::Note => 0,
+            Severity::Warning => 1,
+            Severity::Error => 2,
+        }
+    }
+
+    pub(crate) fn label(&self) -> &'static str {
+        match self {
+            Severity::Note => "note",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+/// A single policy weakness, tied to the directive that caused it.
+pub(crate) struct Finding {
+    pub(crate) severity: Severity,
+    pub(crate) directive: String,
+    pub(crate) message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self.severity {
+            Severity::Error => "error".red().bold(),
+            Severity::Warning => "warning".yellow().bold(),
+            Severity::Note => "note".normal().bold(),
+        };
+        write!(f, "{label}[{}]: {}", self.directive, self.message)
+    }
+}
+
+/// Runs the audit checks against a parsed policy and returns every finding.
+pub(crate) fn audit(rows: &[Row]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    // Compare parsed `Directive`s, not raw key strings: directive names are
+    // case-insensitive, but `Row::key` preserves the input's original case.
+    let find = |key: &str| {
+        let target = Directive::parse(key);
+        rows.iter().find(|row| row.directive == target)
+    };
+
+    for key in ["script-src", "style-src"] {
+        let Some(row) = find(key) else { continue };
+        let texts: Vec<&str> = row.values.iter().map(|value| value.text.as_str()).collect();
+        let has_nonce_or_hash = texts
+            .iter()
+            .any(|text| matches!(tokenize(text), SourceExpression::Nonce | SourceExpression::Hash));
+
+        if texts.contains(&"'unsafe-eval'") {
+            findings.push(Finding {
+                severity: Severity::Warning,
+                directive: key.to_string(),
+                message: format!("'unsafe-eval' in {key} allows calling eval() and similar on arbitrary strings"),
+            });
+        }
+
+        if texts.contains(&"'unsafe-inline'") {
+            if has_nonce_or_hash {
+                findings.push(Finding {
+                    severity: Severity::Note,
+                    directive: key.to_string(),
+                    message: format!(
+                        "'unsafe-inline' in {key} is ignored by browsers that understand the nonce/hash also present there"
+                    ),
+                });
+            } else {
+                findings.push(Finding {
+                    severity: Severity::Warning,
+                    directive: key.to_string(),
+                    message: format!(
+                        "'unsafe-inline' in {key} without a nonce or hash allows any inline script/style to run"
+                    ),
+                });
+            }
+        }
+    }
+
+    for key in FETCH_DIRECTIVES {
+        let Some(row) = find(key) else { continue };
+        for value in &row.values {
+            let message = match value.text.as_str() {
+                "*" => Some(format!("'*' in {key} allows loading from any origin")),
+                "http:" => Some(format!(
+                    "'http:' in {key} allows loading over plain, unencrypted HTTP from any origin"
+                )),
+                "data:" => Some(format!(
+                    "'data:' in {key} allows inline data: URIs, which can smuggle in executable content without ever making a request"
+                )),
+                _ => None,
+            };
+            if let Some(message) = message {
+                findings.push(Finding {
+                    severity: Severity::Warning,
+                    directive: (*key).to_string(),
+                    message,
+                });
+            }
+        }
+    }
+
+    if find("default-src").is_none() && find("script-src").is_none() && find("object-src").is_none() {
+        findings.push(Finding {
+            severity: Severity::Error,
+            directive: "default-src".to_string(),
+            message: "no default-src and no script-src/object-src fallback; unlisted directives fall back to allowing anything".to_string(),
+        });
+    }
+
+    let has_object_src_none = find("object-src")
+        .is_some_and(|row| row.values.iter().any(|value| value.text == "'none'"));
+    if !has_object_src_none {
+        findings.push(Finding {
+            severity: Severity::Warning,
+            directive: "object-src".to_string(),
+            message: "missing `object-src 'none'`; legacy plugin content can still be loaded".to_string(),
+        });
+    }
+
+    if find("frame-ancestors").is_none() {
+        findings.push(Finding {
+            severity: Severity::Warning,
+            directive: "frame-ancestors".to_string(),
+            message: "missing frame-ancestors; the page can be framed by any site".to_string(),
+        });
+    }
+
+    if find("report-uri").is_some() {
+        findings.push(Finding {
+            severity: Severity::Note,
+            directive: "report-uri".to_string(),
+            message: "report-uri is deprecated; use report-to (or reporting-endpoints) instead"
+                .to_string(),
+        });
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Row;
+
+    fn rows(policy: &str) -> Vec<Row> {
+        policy.split(';').flat_map(Row::from).collect()
+    }
+
+    #[test]
+    fn flags_unsafe_inline_without_nonce() {
+        let findings = audit(&rows("script-src 'unsafe-inline'"));
+        assert!(findings
+            .iter()
+            .any(|finding| finding.severity == Severity::Warning
+                && finding.message.contains("unsafe-inline")));
+    }
+
+    #[test]
+    fn notes_unsafe_inline_alongside_nonce() {
+        let findings = audit(&rows("script-src 'unsafe-inline' 'nonce-dGVzdA=='"));
+        assert!(findings
+            .iter()
+            .any(|finding| finding.severity == Severity::Note));
+    }
+
+    #[test]
+    fn flags_missing_fallback_directives() {
+        let findings = audit(&rows("img-src 'self'"));
+        assert!(findings
+            .iter()
+            .any(|finding| finding.severity == Severity::Error));
+    }
+
+    #[test]
+    fn flags_wildcard_with_any_origin_message() {
+        let findings = audit(&rows("img-src *"));
+        assert!(findings
+            .iter()
+            .any(|finding| finding.message.contains("any origin") && finding.message.contains('*')));
+    }
+
+    #[test]
+    fn flags_data_scheme_with_its_own_message_not_any_origin() {
+        let findings = audit(&rows("img-src data:"));
+        assert!(findings.iter().any(|finding| finding.message.contains("data:")
+            && finding.message.contains("smuggle")
+            && !finding.message.contains("any origin")));
+    }
+
+    #[test]
+    fn flags_http_scheme_with_its_own_message() {
+        let findings = audit(&rows("img-src http:"));
+        assert!(findings
+            .iter()
+            .any(|finding| finding.message.contains("http:") && finding.message.contains("unencrypted")));
+    }
+
+    #[test]
+    fn flags_deprecated_report_uri() {
+        let findings = audit(&rows("report-uri /csp-violation-report-endpoint"));
+        assert!(findings
+            .iter()
+            .any(|finding| finding.severity == Severity::Note
+                && finding.message.contains("deprecated")));
+    }
+
+    #[test]
+    fn does_not_flag_a_locked_down_policy() {
+        let findings = audit(&rows(
+            "default-src 'none'; object-src 'none'; frame-ancestors 'none'",
+        ));
+        assert!(findings.is_empty());
+    }
+
+    #[test]
+    fn does_not_flag_a_locked_down_policy_with_mixed_case_directives() {
+        let findings = audit(&rows(
+            "Default-Src 'none'; Object-Src 'none'; Frame-Ancestors 'none'",
+        ));
+        assert!(findings.is_empty());
+    }
+}
@@ -0,0 +1,124 @@
+//! Known CSP directive names, and which source expressions are legal within each one.
+//!
+//! The directive/keyword tables mirror the shape used by the `axum-csp` and
+//! `kvarn` crates: a closed enum of directive names, each with its own set of
+//! permitted keyword sources and whether it accepts scheme/host sources at all.
+
+/// A recognized Content-Security-Policy directive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Directive {
+    DefaultSrc,
+    ScriptSrc,
+    StyleSrc,
+    ImgSrc,
+    ConnectSrc,
+    FontSrc,
+    ObjectSrc,
+    MediaSrc,
+    FrameSrc,
+    WorkerSrc,
+    ManifestSrc,
+    ChildSrc,
+    FormAction,
+    FrameAncestors,
+    BaseUri,
+    Sandbox,
+    ReportUri,
+    ReportTo,
+    UpgradeInsecureRequests,
+    BlockAllMixedContent,
+}
+
+impl Directive {
+    /// Parses a directive name (case-insensitive) into a known `Directive`.
+    pub fn parse(name: &str) -> Option<Directive> {
+        match name.to_lowercase().as_str() {
+            "default-src" => Some(Directive::DefaultSrc),
+            "script-src" => Some(Directive::ScriptSrc),
+            "style-src" => Some(Directive::StyleSrc),
+            "img-src" => Some(Directive::ImgSrc),
+            "connect-src" => Some(Directive::ConnectSrc),
+            "font-src" => Some(Directive::FontSrc),
+            "object-src" => Some(Directive::ObjectSrc),
+            "media-src" => Some(Directive::MediaSrc),
+            "frame-src" => Some(Directive::FrameSrc),
+            "worker-src" => Some(Directive::WorkerSrc),
+            "manifest-src" => Some(Directive::ManifestSrc),
+            "child-src" => Some(Directive::ChildSrc),
+            "form-action" => Some(Directive::FormAction),
+            "frame-ancestors" => Some(Directive::FrameAncestors),
+            "base-uri" => Some(Directive::BaseUri),
+            "sandbox" => Some(Directive::Sandbox),
+            "report-uri" => Some(Directive::ReportUri),
+            // `reporting-endpoints` is the Reporting API's replacement
+            // header for declaring named endpoints, but within a CSP it
+            // plays the same role as `report-to`: a list of group names.
+            "report-to" | "reporting-endpoints" => Some(Directive::ReportTo),
+            "upgrade-insecure-requests" => Some(Directive::UpgradeInsecureRequests),
+            "block-all-mixed-content" => Some(Directive::BlockAllMixedContent),
+            _ => None,
+        }
+    }
+
+    /// Keyword sources that are legal in this directive.
+    pub fn allowed_keywords(&self) -> &'static [&'static str] {
+        use Directive::*;
+        match self {
+            ScriptSrc => &[
+                "'self'",
+                "'none'",
+                "'unsafe-inline'",
+                "'unsafe-eval'",
+                "'strict-dynamic'",
+                "'report-sample'",
+                "'wasm-unsafe-eval'",
+            ],
+            StyleSrc => &["'self'", "'none'", "'unsafe-inline'", "'report-sample'"],
+            DefaultSrc | ImgSrc | ConnectSrc | FontSrc | ObjectSrc | MediaSrc | FrameSrc
+            | WorkerSrc | ManifestSrc | ChildSrc | FormAction | FrameAncestors | BaseUri => {
+                &["'self'", "'none'"]
+            }
+            Sandbox | ReportUri | ReportTo | UpgradeInsecureRequests | BlockAllMixedContent => &[],
+        }
+    }
+
+    /// Whether this directive accepts scheme-sources and host-sources at all
+    /// (as opposed to directives like `sandbox` or the reporting directives,
+    /// which take their own special vocabulary).
+    pub fn allows_sources(&self) -> bool {
+        !matches!(
+            self,
+            Directive::Sandbox
+                | Directive::ReportUri
+                | Directive::ReportTo
+                | Directive::UpgradeInsecureRequests
+                | Directive::BlockAllMixedContent
+        )
+    }
+}
+
+/// The tokens the HTML `iframe sandbox` attribute understands, which
+/// `sandbox` directive values share. These are bare unquoted tokens, not
+/// source-expressions, so they need their own vocabulary check rather than
+/// being run through `tokenize`.
+const SANDBOX_TOKENS: &[&str] = &[
+    "allow-downloads",
+    "allow-forms",
+    "allow-modals",
+    "allow-orientation-lock",
+    "allow-pointer-lock",
+    "allow-popups",
+    "allow-popups-to-escape-sandbox",
+    "allow-presentation",
+    "allow-same-origin",
+    "allow-scripts",
+    "allow-storage-access-by-user-activation",
+    "allow-top-navigation",
+    "allow-top-navigation-by-user-activation",
+    "allow-top-navigation-to-custom-protocols",
+];
+
+/// Whether `value` is a recognized `sandbox` token.
+pub fn is_valid_sandbox_token(value: &str) -> bool {
+    SANDBOX_TOKENS.contains(&value)
+}
@@ -1,58 +1,161 @@
+mod audit;
+mod directive;
+mod pager;
+mod render;
+mod tokenizer;
+
 use clap::Parser;
 use colored::Colorize;
-use regex::Regex;
-use std::io::{self, BufRead};
+use directive::{is_valid_sandbox_token, Directive};
+use render::OutputFormat;
+use std::io::{self, BufRead, IsTerminal};
 use std::iter::FlatMap;
-use std::str::Split;
+use std::process::ExitCode;
+use std::str::{FromStr, Split};
+use tokenizer::{
+    is_valid_group_name, is_valid_hash, is_valid_nonce, is_valid_report_uri, tokenize,
+    SourceExpression,
+};
+
+/// When to colorize output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColorMode {
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorMode {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "auto" => Ok(ColorMode::Auto),
+            "always" => Ok(ColorMode::Always),
+            "never" => Ok(ColorMode::Never),
+            other => Err(format!(
+                "unknown color mode `{other}` (expected auto, always, or never)"
+            )),
+        }
+    }
+}
+
+impl ColorMode {
+    /// Whether output should actually be colorized, resolving `Auto`
+    /// against whether stdout is a terminal.
+    fn should_colorize(&self) -> bool {
+        match self {
+            ColorMode::Always => true,
+            ColorMode::Never => false,
+            ColorMode::Auto => io::stdout().is_terminal(),
+        }
+    }
+}
+
+/// Whether a policy came from an enforcing `Content-Security-Policy` header
+/// or a `Content-Security-Policy-Report-Only` one. A report-only policy
+/// never blocks anything; browsers only send violation reports for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PolicyMode {
+    Enforced,
+    ReportOnly,
+}
 
-struct Row {
-    key: String,
-    values: Vec<Value>,
+pub(crate) struct Row {
+    pub(crate) key: String,
+    pub(crate) directive: Option<Directive>,
+    pub(crate) values: Vec<Value>,
 }
 
 #[derive(Debug)]
-enum ValueType {
+pub(crate) enum ValueType {
     Error,
     Safe,
     UnSafe,
     Plain,
 }
 
-struct Value {
-    text: String,
-    value_type: ValueType,
+pub(crate) struct Value {
+    pub(crate) text: String,
+    pub(crate) value_type: ValueType,
 }
 
 impl Value {
-    fn from(text: &str) -> Value {
+    fn from(key: &str, text: &str) -> Value {
         Value {
             text: text.to_string(),
-            value_type: Value::classify(text),
+            value_type: Value::classify(key, text),
         }
     }
 
-    fn classify(value: &str) -> ValueType {
-        match value {
-            "'self'" => ValueType::Safe,
-            "'none'" => ValueType::Safe,
-            "'unsafe-inline'" => ValueType::UnSafe,
-            "'unsafe-eval'" => ValueType::UnSafe,
-            // It is probably safe to use `data:` in images
-            // but better be safe then sorry.
-            // See: https://security.stackexchange.com/questions/94993/is-including-the-data-scheme-in-your-content-security-policy-safe/167244
-            "data:" => ValueType::UnSafe,
-            _ => match Value::is_url(value) {
-                true => ValueType::Plain,
+    fn classify(key: &str, value: &str) -> ValueType {
+        let directive = Directive::parse(key);
+
+        // These directives don't take source expressions at all: the
+        // reporting directives take a report URI or a named reporting
+        // group, and `sandbox` takes its own bare-token vocabulary.
+        match directive {
+            Some(Directive::ReportUri) => {
+                return match is_valid_report_uri(value) {
+                    true => ValueType::Plain,
+                    false => ValueType::Error,
+                };
+            }
+            Some(Directive::ReportTo) => {
+                return match is_valid_group_name(value) {
+                    true => ValueType::Plain,
+                    false => ValueType::Error,
+                };
+            }
+            // `sandbox` values are bare tokens from the iframe sandbox
+            // vocabulary, not source-expressions.
+            Some(Directive::Sandbox) => {
+                return match is_valid_sandbox_token(value) {
+                    true => ValueType::Plain,
+                    false => ValueType::Error,
+                };
+            }
+            _ => {}
+        }
+
+        match tokenize(value) {
+            SourceExpression::Keyword => match directive {
+                // Known keyword, legal in this directive.
+                Some(d) if d.allowed_keywords().contains(&value) => match value {
+                    "'unsafe-inline'" | "'unsafe-eval'" => ValueType::UnSafe,
+                    _ => ValueType::Safe,
+                },
+                // Either the keyword isn't one this directive understands
+                // (e.g. `'strict-dynamic'` in `img-src`), or the directive
+                // itself is unrecognized.
+                _ => ValueType::Error,
+            },
+            // A well-formed nonce/hash source is exactly what CSP
+            // recommends for allowing inline scripts safely.
+            SourceExpression::Nonce => match is_valid_nonce(value) {
+                true => ValueType::Safe,
+                false => ValueType::Error,
+            },
+            SourceExpression::Hash => match is_valid_hash(value) {
+                true => ValueType::Safe,
+                false => ValueType::Error,
+            },
+            SourceExpression::Scheme => match directive {
+                Some(d) if !d.allows_sources() => ValueType::Error,
+                // It is probably safe to use `data:` in images
+                // but better be safe then sorry.
+                // See: https://security.stackexchange.com/questions/94993/is-including-the-data-scheme-in-your-content-security-policy-safe/167244
+                _ if value == "data:" => ValueType::UnSafe,
+                _ => ValueType::Plain,
+            },
+            SourceExpression::Host => match directive {
+                Some(d) if d.allows_sources() => ValueType::Plain,
                 _ => ValueType::Error,
             },
+            SourceExpression::Malformed => ValueType::Error,
         }
     }
 
-    fn is_url(value: &str) -> bool {
-        let re = Regex::new(r"(https?://)?(\w+\.)+(\w)+").unwrap();
-        re.is_match(value)
-    }
-
     fn pretty(&self) -> String {
         match &self.value_type {
             ValueType::Error => self.text.black().on_red().to_string(),
@@ -65,19 +168,33 @@ impl Value {
 
 impl Row {
     fn from(line: &str) -> Option<Row> {
-        let parts: Vec<_> = line.split_whitespace().collect();
-        if parts.len() < 2 {
-            return None;
-        }
-        let key = parts[0].to_string();
-        let values: Vec<_> = parts[1..].iter().map(|s| Value::from(s)).collect();
-        Some(Row { key, values })
+        let mut parts = line.split_whitespace();
+        let key = parts.next()?.to_string();
+        let directive = Directive::parse(&key);
+        // Boolean directives like `upgrade-insecure-requests` carry no
+        // values at all (and a bare `sandbox` is valid too), so a key with
+        // nothing after it is a perfectly valid row, not garbage to drop.
+        let values: Vec<_> = parts.map(|s| Value::from(&key, s)).collect();
+        Some(Row {
+            key,
+            directive,
+            values,
+        })
     }
 
     fn to_colored_string(&self, separator: &str) -> String {
+        let key = match self.directive {
+            Some(_) => self.key.blue().to_string(),
+            // An unrecognized directive name can't be validated at all, so
+            // flag it the same way we flag a malformed value.
+            None => self.key.black().on_red().to_string(),
+        };
+        if self.values.is_empty() {
+            return key;
+        }
         format!(
             "{}{}{}",
-            self.key.blue(),
+            key,
             separator,
             self.values
                 .iter()
@@ -94,38 +211,113 @@ struct Args {
     /// Show one source per line
     #[clap(short, long)]
     multiline: bool,
+
+    /// Report policy weaknesses and exit non-zero if any are severe
+    #[clap(long)]
+    audit: bool,
+
+    /// Output format
+    #[clap(long, default_value = "text")]
+    output: OutputFormat,
+
+    /// Colorize output: auto, always, or never
+    #[clap(long, default_value = "auto")]
+    color: ColorMode,
+
+    /// Page the output through $PAGER (defaulting to `less -R`)
+    #[clap(long)]
+    pager: bool,
 }
 
-fn main() {
+fn main() -> ExitCode {
     let args = Args::parse();
+    colored::control::set_override(args.color.should_colorize());
 
+    let mut exit_code: u8 = 0;
+    let mut output = String::new();
     let input = io::stdin();
     for line in input.lock().lines() {
-        println!("{}", handle_line(&line.unwrap(), args.multiline));
+        let line = line.unwrap();
+        let (policy, mode) = extract_policy(&line);
+        let rows = parse_rows(&policy);
+        let findings = if args.audit {
+            audit::audit(&rows)
+        } else {
+            Vec::new()
+        };
+
+        match args.output {
+            OutputFormat::Text => {
+                output.push_str(&render::render_text(&rows, args.multiline, mode))
+            }
+            OutputFormat::Json => output.push_str(&render::render_json(&rows, &findings, mode)),
+        }
+        output.push('\n');
+
+        for finding in &findings {
+            eprintln!("{finding}");
+        }
+        if let Some(severity) = findings.iter().map(|finding| finding.severity).max() {
+            exit_code = exit_code.max(severity.exit_code());
+        }
+    }
+
+    if args.pager {
+        pager::show(&output);
+    } else {
+        print!("{output}");
     }
+    ExitCode::from(exit_code)
 }
 
-fn pretty_print(input: &str, multi_line: bool) -> String {
-    let separator = if multi_line { "\n\t" } else { " " };
+fn parse_rows(input: &str) -> Vec<Row> {
     let parts: Split<_> = input.split(';');
     let rows: FlatMap<_, _, _> = parts.flat_map(Row::from);
-    rows.map(|row| row.to_colored_string(separator))
-        .collect::<Vec<_>>()
-        .join(";\n")
+    rows.collect()
 }
 
-fn handle_line(input: &str, multi_line: bool) -> String {
+/// Strips a leading `Content-Security-Policy:` or
+/// `Content-Security-Policy-Report-Only:` header name off a line, leaving
+/// just the policy body and which mode it was declared in. Lines without
+/// either header are assumed to already be a bare, enforced policy body.
+fn extract_policy(input: &str) -> (String, PolicyMode) {
+    // Only the header name is case-insensitive; the policy body isn't (a
+    // nonce/hash body or a report-uri path is case-sensitive), so lowercase
+    // just enough to find the header and slice the original-case remainder.
     let normalised_input = input.to_lowercase();
-    let values = normalised_input.split("content-security-policy:").nth(1);
-    match values {
-        None => pretty_print(input, multi_line),
-        Some(value) => pretty_print(value, multi_line),
+    const REPORT_ONLY_HEADER: &str = "content-security-policy-report-only:";
+    const ENFORCED_HEADER: &str = "content-security-policy:";
+
+    if let Some(start) = normalised_input.find(REPORT_ONLY_HEADER) {
+        let body_start = start + REPORT_ONLY_HEADER.len();
+        return (input[body_start..].to_string(), PolicyMode::ReportOnly);
+    }
+    match normalised_input.find(ENFORCED_HEADER) {
+        None => (input.to_string(), PolicyMode::Enforced),
+        Some(start) => {
+            let body_start = start + ENFORCED_HEADER.len();
+            (input[body_start..].to_string(), PolicyMode::Enforced)
+        }
     }
 }
 
+#[cfg(test)]
+fn pretty_print(input: &str, multi_line: bool) -> String {
+    render::render_text(&parse_rows(input), multi_line, PolicyMode::Enforced)
+}
+
+#[cfg(test)]
+fn handle_line(input: &str, multi_line: bool) -> String {
+    let (policy, mode) = extract_policy(input);
+    render::render_text(&parse_rows(&policy), multi_line, mode)
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::{handle_line, pretty_print, Value, ValueType};
+    use crate::{
+        extract_policy, handle_line, pretty_print, ColorMode, PolicyMode, Value, ValueType,
+    };
+    use std::str::FromStr;
 
     #[test]
     fn setup() {
@@ -138,7 +330,7 @@ mod tests {
 
     #[test]
     fn it_returns_empty_for_empty_string() {
-        let result = pretty_print(&String::from(""), false);
+        let result = pretty_print("", false);
         assert_eq!(result, "");
     }
 
@@ -150,6 +342,14 @@ mod tests {
         assert_eq!(result, expected_value);
     }
 
+    #[test]
+    fn it_renders_boolean_directives_without_a_value() {
+        let input = String::from("upgrade-insecure-requests; block-all-mixed-content");
+        let result = pretty_print(&input, false);
+        let expected_value = "upgrade-insecure-requests;\nblock-all-mixed-content";
+        assert_eq!(result, expected_value);
+    }
+
     // Examples taken from MDN: https://developer.mozilla.org/en-US/docs/Web/HTTP/CSP
     #[test]
     fn it_extracts_from_header() {
@@ -185,27 +385,144 @@ mod tests {
         assert_eq!(result, expected_value);
     }
 
+    #[test]
+    fn it_extracts_from_report_only_header() {
+        let input =
+            String::from("Content-Security-Policy-Report-Only: default-src 'self'");
+        let result = handle_line(&input, false);
+        let expected_value = "[report-only]\ndefault-src 'self'";
+        assert_eq!(result, expected_value);
+    }
+
+    #[test]
+    fn it_preserves_value_case_when_stripping_the_header() {
+        let (policy, mode) =
+            extract_policy("Content-Security-Policy: script-src 'nonce-AbCdEfGh=='");
+        assert_eq!(policy, " script-src 'nonce-AbCdEfGh=='");
+        assert_eq!(mode, PolicyMode::Enforced);
+    }
+
     #[test]
     fn value_classifies_unsafe_inline() {
-        let value = Value::from("'unsafe-inline'");
+        let value = Value::from("script-src", "'unsafe-inline'");
         assert!(matches!(value.value_type, ValueType::UnSafe));
     }
 
     #[test]
     fn value_classifies_unknown_prop() {
-        let value = Value::from("'unsafe-foobar'");
+        let value = Value::from("script-src", "'unsafe-foobar'");
         assert!(matches!(value.value_type, ValueType::Error));
     }
 
     #[test]
     fn value_classifies_proper_url() {
-        let value = Value::from("'https://foo.bar'");
+        let value = Value::from("default-src", "https://foo.bar");
+        assert!(matches!(value.value_type, ValueType::Plain));
+    }
+
+    #[test]
+    fn value_classifies_single_label_host_as_plain() {
+        let value = Value::from("default-src", "localhost");
         assert!(matches!(value.value_type, ValueType::Plain));
     }
 
     #[test]
     fn value_classifies_invalid_url() {
-        let value = Value::from("'https://foo'");
+        let value = Value::from("default-src", "https://foo_bar.com");
+        assert!(matches!(value.value_type, ValueType::Error));
+    }
+
+    #[test]
+    fn value_classifies_strict_dynamic_in_script_src_as_safe() {
+        let value = Value::from("script-src", "'strict-dynamic'");
+        assert!(matches!(value.value_type, ValueType::Safe));
+    }
+
+    #[test]
+    fn value_classifies_strict_dynamic_in_img_src_as_error() {
+        let value = Value::from("img-src", "'strict-dynamic'");
+        assert!(matches!(value.value_type, ValueType::Error));
+    }
+
+    #[test]
+    fn value_classifies_keyword_in_unknown_directive_as_error() {
+        let value = Value::from("not-a-directive", "'self'");
+        assert!(matches!(value.value_type, ValueType::Error));
+    }
+
+    #[test]
+    fn value_classifies_well_formed_nonce_as_safe() {
+        let value = Value::from("script-src", "'nonce-dGVzdA=='");
+        assert!(matches!(value.value_type, ValueType::Safe));
+    }
+
+    #[test]
+    fn value_classifies_empty_nonce_as_error() {
+        let value = Value::from("script-src", "'nonce-'");
+        assert!(matches!(value.value_type, ValueType::Error));
+    }
+
+    #[test]
+    fn value_classifies_well_formed_hash_as_safe() {
+        let value = Value::from(
+            "script-src",
+            "'sha256-MTIzNDU2Nzg5MDEyMzQ1Njc4OTAxMjM0NTY3ODkwMTI='",
+        );
+        assert!(matches!(value.value_type, ValueType::Safe));
+    }
+
+    #[test]
+    fn value_classifies_hash_with_wrong_digest_length_as_error() {
+        let value = Value::from("script-src", "'sha256-dGVzdA=='");
+        assert!(matches!(value.value_type, ValueType::Error));
+    }
+
+    #[test]
+    fn value_classifies_report_uri_as_plain() {
+        let value = Value::from("report-uri", "/csp-violation-report-endpoint");
+        assert!(matches!(value.value_type, ValueType::Plain));
+    }
+
+    #[test]
+    fn value_classifies_empty_report_uri_as_error() {
+        let value = Value::from("report-uri", "");
         assert!(matches!(value.value_type, ValueType::Error));
     }
+
+    #[test]
+    fn value_classifies_report_to_group_name_as_plain() {
+        let value = Value::from("report-to", "csp-endpoint");
+        assert!(matches!(value.value_type, ValueType::Plain));
+    }
+
+    #[test]
+    fn value_classifies_reporting_endpoints_group_name_as_plain() {
+        let value = Value::from("reporting-endpoints", "csp-endpoint");
+        assert!(matches!(value.value_type, ValueType::Plain));
+    }
+
+    #[test]
+    fn value_classifies_known_sandbox_token_as_plain() {
+        let value = Value::from("sandbox", "allow-scripts");
+        assert!(matches!(value.value_type, ValueType::Plain));
+    }
+
+    #[test]
+    fn value_classifies_unknown_sandbox_token_as_error() {
+        let value = Value::from("sandbox", "allow-everything");
+        assert!(matches!(value.value_type, ValueType::Error));
+    }
+
+    #[test]
+    fn color_mode_always_and_never_ignore_the_terminal() {
+        assert!(ColorMode::Always.should_colorize());
+        assert!(!ColorMode::Never.should_colorize());
+    }
+
+    #[test]
+    fn color_mode_parses_from_str() {
+        assert_eq!(ColorMode::from_str("always"), Ok(ColorMode::Always));
+        assert_eq!(ColorMode::from_str("never"), Ok(ColorMode::Never));
+        assert!(ColorMode::from_str("rainbow").is_err());
+    }
 }
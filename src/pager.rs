@@ -0,0 +1,40 @@
+//! Pipes rendered output through `$PAGER` (`less -R` by default) when
+//! requested, falling back to printing directly when no pager is available
+//! or stdout isn't a terminal to page to.
+
+use std::io::{IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+/// Writes `output` to the user's pager, or straight to stdout if paging
+/// isn't possible.
+pub(crate) fn show(output: &str) {
+    if !std::io::stdout().is_terminal() || !try_show_in_pager(output) {
+        print!("{output}");
+    }
+}
+
+fn try_show_in_pager(output: &str) -> bool {
+    let pager = std::env::var("PAGER").unwrap_or_else(|_| "less -R".to_string());
+    let mut parts = pager.split_whitespace();
+    let Some(command) = parts.next() else {
+        return false;
+    };
+
+    let Ok(mut child) = Command::new(command)
+        .args(parts)
+        .stdin(Stdio::piped())
+        .spawn()
+    else {
+        return false;
+    };
+
+    let Some(mut stdin) = child.stdin.take() else {
+        return false;
+    };
+    if stdin.write_all(output.as_bytes()).is_err() {
+        return false;
+    }
+    drop(stdin);
+
+    child.wait().is_ok_and(|status| status.success())
+}
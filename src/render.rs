@@ -0,0 +1,233 @@
+//! Rendering of a parsed policy: colored text for a terminal, or structured
+//! JSON for piping into other tools. Both renderers work off the same
+//! `Vec<Row>` the parser produces, so adding a new output format is just
+//! adding a new function here.
+
+use crate::audit::Finding;
+use crate::directive::Directive;
+use crate::tokenizer::{tokenize, SourceExpression};
+use crate::{PolicyMode, Row, Value, ValueType};
+use serde::Serialize;
+use std::str::FromStr;
+
+/// Which renderer to use for the parsed policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OutputFormat {
+    Text,
+    Json,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            other => Err(format!("unknown output format `{other}` (expected text or json)")),
+        }
+    }
+}
+
+/// Renders a parsed policy as colored text, one directive per line (or one
+/// source per line, in multiline mode). A report-only policy gets a leading
+/// `[report-only]` marker, since it doesn't block anything on its own.
+pub(crate) fn render_text(rows: &[Row], multi_line: bool, mode: PolicyMode) -> String {
+    let separator = if multi_line { "\n\t" } else { " " };
+    let body = rows
+        .iter()
+        .map(|row| row.to_colored_string(separator))
+        .collect::<Vec<_>>()
+        .join(";\n");
+
+    match mode {
+        PolicyMode::ReportOnly if !body.is_empty() => format!("[report-only]\n{body}"),
+        _ => body,
+    }
+}
+
+#[derive(Serialize)]
+struct JsonPolicy {
+    mode: &'static str,
+    directives: Vec<JsonDirective>,
+    /// Findings about a directive that isn't present in the policy at all
+    /// (e.g. a missing `default-src` or `object-src 'none'`), so they have
+    /// no row to nest under.
+    findings: Vec<JsonFinding>,
+}
+
+#[derive(Serialize)]
+struct JsonDirective {
+    name: String,
+    values: Vec<JsonValue>,
+    findings: Vec<JsonFinding>,
+}
+
+#[derive(Serialize)]
+struct JsonValue {
+    text: String,
+    #[serde(rename = "type")]
+    value_type: &'static str,
+}
+
+#[derive(Serialize)]
+struct JsonFinding {
+    severity: &'static str,
+    message: String,
+}
+
+/// Renders a parsed policy as structured JSON: one entry per directive, with
+/// its values annotated by classified type, and any audit findings attached.
+pub(crate) fn render_json(rows: &[Row], findings: &[Finding], mode: PolicyMode) -> String {
+    let to_json_finding = |finding: &Finding| JsonFinding {
+        severity: finding.severity.label(),
+        message: finding.message.clone(),
+    };
+
+    // Findings carry a canonical directive name (e.g. "default-src"), but a
+    // row's key keeps whatever case the input used, so compare parsed
+    // `Directive`s rather than raw strings.
+    let matches_row = |finding: &Finding, row: &Row| {
+        Directive::parse(&finding.directive).is_some_and(|directive| row.directive == Some(directive))
+    };
+
+    let directives = rows
+        .iter()
+        .map(|row| JsonDirective {
+            name: row.key.clone(),
+            values: row.values.iter().map(json_value).collect(),
+            findings: findings
+                .iter()
+                .filter(|finding| matches_row(finding, row))
+                .map(to_json_finding)
+                .collect(),
+        })
+        .collect();
+
+    let policy_findings = findings
+        .iter()
+        .filter(|finding| !rows.iter().any(|row| matches_row(finding, row)))
+        .map(to_json_finding)
+        .collect();
+
+    let mode = match mode {
+        PolicyMode::Enforced => "enforced",
+        PolicyMode::ReportOnly => "report-only",
+    };
+
+    serde_json::to_string_pretty(&JsonPolicy {
+        mode,
+        directives,
+        findings: policy_findings,
+    })
+    .expect("a parsed policy always serializes to JSON")
+}
+
+fn json_value(value: &Value) -> JsonValue {
+    // A malformed nonce/hash is still classified `Error` by `Value::classify`;
+    // only label it "nonce"/"hash" once it's actually valid, so a CI check
+    // parsing this JSON can't mistake a broken one for a working source.
+    let value_type = match (tokenize(&value.text), &value.value_type) {
+        (SourceExpression::Nonce, ValueType::Safe) => "nonce",
+        (SourceExpression::Hash, ValueType::Safe) => "hash",
+        _ => match &value.value_type {
+            ValueType::Safe => "safe",
+            ValueType::UnSafe => "unsafe",
+            ValueType::Plain => "plain",
+            ValueType::Error => "error",
+        },
+    };
+    JsonValue {
+        text: value.text.clone(),
+        value_type,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_output_format() {
+        assert_eq!(OutputFormat::from_str("text"), Ok(OutputFormat::Text));
+        assert_eq!(OutputFormat::from_str("json"), Ok(OutputFormat::Json));
+        assert!(OutputFormat::from_str("yaml").is_err());
+    }
+
+    #[test]
+    fn renders_json_with_classified_values_and_findings() {
+        let rows = crate::parse_rows("script-src 'unsafe-inline'");
+        let findings = crate::audit::audit(&rows);
+        let json = render_json(&rows, &findings, PolicyMode::Enforced);
+
+        assert!(json.contains("\"mode\": \"enforced\""));
+        assert!(json.contains("\"name\": \"script-src\""));
+        assert!(json.contains("\"type\": \"unsafe\""));
+        assert!(json.contains("\"severity\": \"warning\""));
+    }
+
+    #[test]
+    fn renders_json_report_only_mode() {
+        let rows = crate::parse_rows("default-src 'self'");
+        let json = render_json(&rows, &[], PolicyMode::ReportOnly);
+
+        assert!(json.contains("\"mode\": \"report-only\""));
+    }
+
+    #[test]
+    fn renders_policy_level_findings_for_missing_directives() {
+        let rows = crate::parse_rows("img-src 'self'");
+        let findings = crate::audit::audit(&rows);
+        let json = render_json(&rows, &findings, PolicyMode::Enforced);
+
+        assert!(!findings.is_empty());
+        assert!(json.contains("\"findings\": ["));
+        assert!(json.contains("no default-src"));
+        assert!(json.contains("missing `object-src 'none'`"));
+        assert!(json.contains("missing frame-ancestors"));
+    }
+
+    #[test]
+    fn nests_findings_under_mixed_case_directive_row() {
+        let rows = crate::parse_rows("Script-Src 'unsafe-inline'");
+        let findings = crate::audit::audit(&rows);
+        let json = render_json(&rows, &findings, PolicyMode::Enforced);
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        let script_src = &parsed["directives"][0];
+        assert_eq!(script_src["name"], "Script-Src");
+        assert!(script_src["findings"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|finding| finding["message"].as_str().unwrap().contains("unsafe-inline")));
+
+        // Must not also be bucketed into the policy-level findings list.
+        assert!(!parsed["findings"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|finding| finding["message"].as_str().unwrap().contains("unsafe-inline")));
+    }
+
+    #[test]
+    fn reports_malformed_nonce_and_hash_as_error_type() {
+        let rows = crate::parse_rows("script-src 'nonce-' 'sha256-dGVzdA=='");
+        let json = render_json(&rows, &[], PolicyMode::Enforced);
+
+        assert!(!json.contains("\"type\": \"nonce\""));
+        assert!(!json.contains("\"type\": \"hash\""));
+        assert_eq!(json.matches("\"type\": \"error\"").count(), 2);
+    }
+
+    #[test]
+    fn reports_well_formed_nonce_and_hash_as_their_own_type() {
+        let rows = crate::parse_rows(
+            "script-src 'nonce-dGVzdA==' 'sha256-MTIzNDU2Nzg5MDEyMzQ1Njc4OTAxMjM0NTY3ODkwMTI='",
+        );
+        let json = render_json(&rows, &[], PolicyMode::Enforced);
+
+        assert!(json.contains("\"type\": \"nonce\""));
+        assert!(json.contains("\"type\": \"hash\""));
+    }
+}
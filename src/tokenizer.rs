@@ -0,0 +1,281 @@
+//! A small hand-written tokenizer for CSP source-expression grammars.
+//!
+//! Replaces a single loose "looks like a URL" regex with something that
+//! actually recognizes the source-expression productions from the CSP spec:
+//! scheme-source, host-source, keyword-source, nonce-source and
+//! hash-source. This lets callers tell these classes apart instead of
+//! lumping everything that isn't a known keyword into "plain" or "error".
+
+/// The source-expression grammar a value matched, if any.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceExpression {
+    /// A scheme-source, e.g. `https:`, `data:`, `blob:`.
+    Scheme,
+    /// A host-source, e.g. `*`, `*.example.com`, `https://example.com:443/path`.
+    Host,
+    /// A quoted keyword-source, e.g. `'self'`, `'unsafe-inline'`.
+    Keyword,
+    /// A quoted nonce-source, e.g. `'nonce-<base64>'`.
+    Nonce,
+    /// A quoted hash-source, e.g. `'sha256-<base64>'`.
+    Hash,
+    /// Doesn't match any known source-expression grammar.
+    Malformed,
+}
+
+/// Classifies a single CSP source-expression token.
+pub fn tokenize(value: &str) -> SourceExpression {
+    if let Some(inner) = unquote(value) {
+        if inner.starts_with("nonce-") {
+            return SourceExpression::Nonce;
+        }
+        if inner.starts_with("sha256-") || inner.starts_with("sha384-") || inner.starts_with("sha512-") {
+            return SourceExpression::Hash;
+        }
+        return SourceExpression::Keyword;
+    }
+
+    if is_scheme_source(value) {
+        return SourceExpression::Scheme;
+    }
+
+    if is_host_source(value) {
+        return SourceExpression::Host;
+    }
+
+    SourceExpression::Malformed
+}
+
+/// Strips the single quotes off a quoted-source token, e.g. `'self'` -> `self`.
+fn unquote(value: &str) -> Option<&str> {
+    value
+        .strip_prefix('\'')
+        .and_then(|rest| rest.strip_suffix('\''))
+        .filter(|inner| !inner.is_empty())
+}
+
+/// `scheme-source = scheme-part ":"`, e.g. `https:`, `data:`, `blob:`.
+fn is_scheme_source(value: &str) -> bool {
+    match value.strip_suffix(':') {
+        Some(scheme_part) if !scheme_part.is_empty() && !value.contains('/') => {
+            let mut chars = scheme_part.chars();
+            chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+                && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        }
+        _ => false,
+    }
+}
+
+/// `host-source = [ scheme-part "://" ] host-part [ "/" path-part ]`, where
+/// `host-part` may be a bare `*`, start with `*.`, and carry an optional
+/// `:port` or `:*`.
+fn is_host_source(value: &str) -> bool {
+    let without_scheme = match value.split_once("://") {
+        Some((scheme_part, rest)) => {
+            if !is_scheme_name(scheme_part) {
+                return false;
+            }
+            rest
+        }
+        None => value,
+    };
+
+    let host_and_port = without_scheme.split('/').next().unwrap_or("");
+    if host_and_port.is_empty() {
+        return false;
+    }
+
+    let host = match host_and_port.rsplit_once(':') {
+        Some((host, port)) if !host.is_empty() && is_port(port) => host,
+        _ => host_and_port,
+    };
+
+    if host == "*" {
+        return true;
+    }
+
+    let host = host.strip_prefix("*.").unwrap_or(host);
+    let labels: Vec<_> = host.split('.').collect();
+    // The CSP host-part grammar doesn't require a dot: a bare single label
+    // like `localhost` is a legitimate host-source on its own.
+    !labels.is_empty() && labels.iter().all(|label| is_host_label(label))
+}
+
+fn is_scheme_name(value: &str) -> bool {
+    let mut chars = value.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+fn is_port(value: &str) -> bool {
+    value == "*" || (!value.is_empty() && value.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn is_host_label(label: &str) -> bool {
+    !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// The three hash algorithms CSP recognizes for hash-sources, and the
+/// digest length (in bytes) each one produces.
+const HASH_ALGORITHMS: &[(&str, usize)] = &[("sha256-", 32), ("sha384-", 48), ("sha512-", 64)];
+
+/// Checks that a `'nonce-<base64-value>'` token carries a well-formed,
+/// non-empty base64 body.
+pub fn is_valid_nonce(value: &str) -> bool {
+    unquote(value)
+        .and_then(|inner| inner.strip_prefix("nonce-"))
+        .is_some_and(is_base64)
+}
+
+/// Checks that a `'sha256-/sha384-/sha512-<base64-value>'` token has a body
+/// that decodes to exactly the digest length its algorithm produces.
+pub fn is_valid_hash(value: &str) -> bool {
+    let Some(inner) = unquote(value) else {
+        return false;
+    };
+    HASH_ALGORITHMS.iter().any(|(prefix, digest_len)| {
+        inner
+            .strip_prefix(prefix)
+            .is_some_and(|body| is_base64(body) && base64_decoded_len(body) == Some(*digest_len))
+    })
+}
+
+/// Non-empty and uses only the base64 alphabet (letters, digits, `+`, `/`,
+/// `=` padding).
+fn is_base64(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '/' | '='))
+}
+
+/// The decoded byte length of a base64 string, accounting for padding, or
+/// `None` if its length isn't a valid base64 multiple of four.
+fn base64_decoded_len(value: &str) -> Option<usize> {
+    if value.is_empty() || !value.len().is_multiple_of(4) {
+        return None;
+    }
+    let padding = value.chars().rev().take_while(|&c| c == '=').count();
+    Some(value.len() / 4 * 3 - padding)
+}
+
+/// A `report-uri` value: an absolute or relative URI reference. We don't
+/// implement full RFC 3986, just reject the empty/whitespace-containing
+/// garbage that can't possibly be a URI.
+pub fn is_valid_report_uri(value: &str) -> bool {
+    !value.is_empty() && !value.chars().any(char::is_whitespace)
+}
+
+/// A `report-to`/`reporting-endpoints` value: an opaque group name defined
+/// elsewhere by a `Report-To`/`Reporting-Endpoints` header, not a source
+/// expression.
+pub fn is_valid_group_name(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_scheme_sources() {
+        assert!(matches!(tokenize("https:"), SourceExpression::Scheme));
+        assert!(matches!(tokenize("data:"), SourceExpression::Scheme));
+        assert!(matches!(tokenize("blob:"), SourceExpression::Scheme));
+    }
+
+    #[test]
+    fn recognizes_host_sources() {
+        assert!(matches!(tokenize("*"), SourceExpression::Host));
+        assert!(matches!(tokenize("foo.bar"), SourceExpression::Host));
+        assert!(matches!(tokenize("*.example.com"), SourceExpression::Host));
+        assert!(matches!(
+            tokenize("*.example.com:443"),
+            SourceExpression::Host
+        ));
+        assert!(matches!(
+            tokenize("https://example.com/some/path"),
+            SourceExpression::Host
+        ));
+    }
+
+    #[test]
+    fn recognizes_single_label_hosts() {
+        // The host-part grammar doesn't require a dot: `localhost` is a
+        // common, legitimate host-source on its own.
+        assert!(matches!(tokenize("localhost"), SourceExpression::Host));
+        assert!(matches!(
+            tokenize("http://localhost"),
+            SourceExpression::Host
+        ));
+        assert!(matches!(
+            tokenize("http://localhost:8080"),
+            SourceExpression::Host
+        ));
+    }
+
+    #[test]
+    fn rejects_hosts_with_invalid_label_characters() {
+        assert!(matches!(
+            tokenize("https://foo_bar.com"),
+            SourceExpression::Malformed
+        ));
+    }
+
+    #[test]
+    fn recognizes_keyword_sources() {
+        assert!(matches!(tokenize("'self'"), SourceExpression::Keyword));
+        assert!(matches!(
+            tokenize("'unsafe-inline'"),
+            SourceExpression::Keyword
+        ));
+    }
+
+    #[test]
+    fn recognizes_nonce_and_hash_sources() {
+        assert!(matches!(
+            tokenize("'nonce-abc123=='"),
+            SourceExpression::Nonce
+        ));
+        assert!(matches!(
+            tokenize("'sha256-abc123=='"),
+            SourceExpression::Hash
+        ));
+    }
+
+    #[test]
+    fn validates_nonce_body() {
+        assert!(is_valid_nonce("'nonce-dGVzdA=='"));
+        assert!(!is_valid_nonce("'nonce-'"));
+        assert!(!is_valid_nonce("'nonce-not base64!'"));
+    }
+
+    #[test]
+    fn validates_report_uri() {
+        assert!(is_valid_report_uri("/csp-violation-report-endpoint"));
+        assert!(is_valid_report_uri("https://example.com/csp-reports"));
+        assert!(!is_valid_report_uri(""));
+        assert!(!is_valid_report_uri("not a uri"));
+    }
+
+    #[test]
+    fn validates_group_name() {
+        assert!(is_valid_group_name("csp-endpoint"));
+        assert!(!is_valid_group_name(""));
+        assert!(!is_valid_group_name("has spaces"));
+    }
+
+    #[test]
+    fn validates_hash_body_and_digest_length() {
+        // 32 bytes, base64-encoded to 44 chars: a real sha256 digest length.
+        assert!(is_valid_hash(
+            "'sha256-MTIzNDU2Nzg5MDEyMzQ1Njc4OTAxMjM0NTY3ODkwMTI='"
+        ));
+        // Right charset, wrong digest length for sha256.
+        assert!(!is_valid_hash("'sha256-dGVzdA=='"));
+        assert!(!is_valid_hash("'sha256-'"));
+    }
+}